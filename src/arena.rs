@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::{get_time, rand};
+
+use crate::{combat, Battler, EAttribute, Status};
+
+/// Exploration constant for UCB1 (`avg_score + C * sqrt(ln(parent_visits) / child_visits)`).
+const EXPLORATION_C: f64 = 1.4;
+/// Wall-clock budget for one MCTS search, run each time the AI needs to pick a move.
+const SEARCH_BUDGET_SECS: f64 = 0.05;
+/// Damage multiplier for a saved burst move.
+const BURST_MULTIPLIER: f64 = 2.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Player,
+    Ai,
+}
+
+impl Side {
+    fn other(self) -> Side {
+        match self {
+            Side::Player => Side::Ai,
+            Side::Ai => Side::Player,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Combatant {
+    status: Status,
+    attribute: EAttribute,
+    hp: i64,
+}
+
+impl Combatant {
+    fn from_battler(b: &Battler) -> Self {
+        Self {
+            status: b.data.get_power(),
+            attribute: b.data.attribute.clone(),
+            hp: b.s_hp.hp as i64,
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        self.hp > 0
+    }
+}
+
+/// A cloneable snapshot of an arena battle, transitioned one `Move` at a time.
+#[derive(Clone)]
+pub struct BattleState {
+    player_team: Vec<Combatant>,
+    ai_team: Vec<Combatant>,
+    ai_burst_used: bool,
+}
+
+impl BattleState {
+    pub fn new(player_team: &[Battler], ai_team: &[Battler]) -> Self {
+        Self {
+            player_team: player_team.iter().map(Combatant::from_battler).collect(),
+            ai_team: ai_team.iter().map(Combatant::from_battler).collect(),
+            ai_burst_used: false,
+        }
+    }
+
+    fn team(&self, side: Side) -> &[Combatant] {
+        match side {
+            Side::Player => &self.player_team,
+            Side::Ai => &self.ai_team,
+        }
+    }
+
+    fn team_mut(&mut self, side: Side) -> &mut Vec<Combatant> {
+        match side {
+            Side::Player => &mut self.player_team,
+            Side::Ai => &mut self.ai_team,
+        }
+    }
+
+    pub fn winner(&self) -> Option<Side> {
+        let player_alive = self.player_team.iter().any(Combatant::is_alive);
+        let ai_alive = self.ai_team.iter().any(Combatant::is_alive);
+        match (player_alive, ai_alive) {
+            (true, false) => Some(Side::Player),
+            (false, true) => Some(Side::Ai),
+            _ => None,
+        }
+    }
+
+    fn legal_moves(&self, side: Side) -> Vec<Move> {
+        let attackers: Vec<usize> = self
+            .team(side)
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_alive())
+            .map(|(i, _)| i)
+            .collect();
+        let targets: Vec<usize> = self
+            .team(side.other())
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_alive())
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut moves = Vec::new();
+        for &attacker_idx in &attackers {
+            for &target_idx in &targets {
+                moves.push(Move {
+                    attacker_idx,
+                    target_idx,
+                    use_burst: false,
+                });
+                if side == Side::Ai && !self.ai_burst_used {
+                    moves.push(Move {
+                        attacker_idx,
+                        target_idx,
+                        use_burst: true,
+                    });
+                }
+            }
+        }
+        moves
+    }
+
+    fn apply(&self, mv: &Move, side: Side) -> BattleState {
+        let mut next = self.clone();
+        let attacker = next.team(side)[mv.attacker_idx].clone();
+        let defender = next.team(side.other())[mv.target_idx].clone();
+
+        let mut dmg = combat::resolve_damage(
+            &attacker.status,
+            &attacker.attribute,
+            defender.status.def,
+            &defender.attribute,
+        ) as f64;
+        if mv.use_burst {
+            dmg *= BURST_MULTIPLIER;
+            next.ai_burst_used = true;
+        }
+
+        let target = &mut next.team_mut(side.other())[mv.target_idx];
+        target.hp = (target.hp - dmg as i64).max(0);
+        next
+    }
+}
+
+/// Which pet attacks, who it targets, and whether it spends the AI's one saved burst.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Move {
+    attacker_idx: usize,
+    target_idx: usize,
+    use_burst: bool,
+}
+
+struct Node {
+    state: BattleState,
+    side_to_move: Side,
+    visit_count: u32,
+    score_sum: f64,
+    children: HashMap<Move, Node>,
+    unexplored: Vec<Move>,
+}
+
+impl Node {
+    fn new(state: BattleState, side_to_move: Side) -> Self {
+        let unexplored = state.legal_moves(side_to_move);
+        Self {
+            state,
+            side_to_move,
+            visit_count: 0,
+            score_sum: 0.0,
+            children: HashMap::new(),
+            unexplored,
+        }
+    }
+
+    /// UCB1 score used to pick which child to descend into during selection,
+    /// from `perspective`'s point of view. `score_sum` is always tallied in
+    /// AI-perspective terms (win = 1), so a node where the player is choosing
+    /// uses `1 - avg` instead: the player is modeled as picking the branch that
+    /// minimizes the AI's score, not maximizing it.
+    fn ucb1(&self, parent_visits: u32, perspective: Side) -> f64 {
+        if self.visit_count == 0 {
+            return f64::INFINITY;
+        }
+        let avg = self.score_sum / self.visit_count as f64;
+        let value = match perspective {
+            Side::Ai => avg,
+            Side::Player => 1.0 - avg,
+        };
+        value + EXPLORATION_C * ((parent_visits as f64).ln() / self.visit_count as f64).sqrt()
+    }
+}
+
+/// One MCTS iteration: select down to an expandable node, expand it, simulate
+/// a random playout, then backpropagate the result up the path just walked.
+fn run_iteration(node: &mut Node) -> f64 {
+    if let Some(winner) = node.state.winner() {
+        let score = if winner == Side::Ai { 1.0 } else { 0.0 };
+        node.visit_count += 1;
+        node.score_sum += score;
+        return score;
+    }
+
+    if let Some(mv) = node.unexplored.pop() {
+        let next_state = node.state.apply(&mv, node.side_to_move);
+        let next_side = node.side_to_move.other();
+        let score = simulate(next_state.clone(), next_side);
+
+        let mut child = Node::new(next_state, next_side);
+        child.visit_count = 1;
+        child.score_sum = score;
+        node.children.insert(mv, child);
+
+        node.visit_count += 1;
+        node.score_sum += score;
+        return score;
+    }
+
+    let parent_visits = node.visit_count.max(1);
+    let perspective = node.side_to_move;
+    let best_move = node
+        .children
+        .iter()
+        .max_by(|(_, a), (_, b)| {
+            a.ucb1(parent_visits, perspective)
+                .partial_cmp(&b.ucb1(parent_visits, perspective))
+                .unwrap()
+        })
+        .map(|(mv, _)| mv.clone());
+
+    let Some(best_move) = best_move else {
+        // No unexplored moves and no children: the node is terminal in all
+        // but name (e.g. the team that can act has nobody left to target).
+        return 0.5;
+    };
+
+    let child = node.children.get_mut(&best_move).unwrap();
+    let score = run_iteration(child);
+
+    node.visit_count += 1;
+    node.score_sum += score;
+    score
+}
+
+/// Plays random legal moves from `state` until one team is wiped, scoring the
+/// outcome from the AI's perspective (win = 1, loss = 0).
+fn simulate(mut state: BattleState, mut side: Side) -> f64 {
+    loop {
+        if let Some(winner) = state.winner() {
+            return if winner == Side::Ai { 1.0 } else { 0.0 };
+        }
+
+        let moves = state.legal_moves(side);
+        let Some(mv) = moves.get(rand::gen_range(0, moves.len())) else {
+            return 0.5;
+        };
+        state = state.apply(mv, side);
+        side = side.other();
+    }
+}
+
+fn run_search(root: &mut Node) {
+    let deadline = get_time() + SEARCH_BUDGET_SECS;
+    while get_time() < deadline {
+        run_iteration(root);
+    }
+}
+
+fn most_visited_move(root: &Node) -> Option<Move> {
+    root.children
+        .iter()
+        .max_by_key(|(_, child)| child.visit_count)
+        .map(|(mv, _)| mv.clone())
+}
+
+/// Picks the strongest living attacker and aims it at the weakest living
+/// target. Drives the player's side of an arena match without needing input.
+fn pick_heuristic_move(state: &BattleState, side: Side) -> Option<Move> {
+    let attacker_idx = state
+        .team(side)
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.is_alive())
+        .max_by_key(|(_, c)| c.status.str)?
+        .0;
+
+    let target_idx = state
+        .team(side.other())
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.is_alive())
+        .min_by_key(|(_, c)| c.hp)?
+        .0;
+
+    Some(Move {
+        attacker_idx,
+        target_idx,
+        use_burst: false,
+    })
+}
+
+/// An ongoing arena match: the player's team against an MCTS-controlled AI team.
+pub struct ArenaMatch {
+    state: BattleState,
+    // The AI's previous-turn search tree, rooted at the state right after its
+    // own last move. Reused as the next root if the player's actual move
+    // matches a branch it already explored, so work isn't thrown away.
+    cached_root: Option<Node>,
+    pub log: Vec<String>,
+    pub winner: Option<Side>,
+}
+
+impl ArenaMatch {
+    pub fn new(player_team: &[Battler], ai_team: &[Battler]) -> Self {
+        Self {
+            state: BattleState::new(player_team, ai_team),
+            cached_root: None,
+            log: Vec::new(),
+            winner: None,
+        }
+    }
+
+    /// Advances the match by one full round: the player's team swings first,
+    /// then the AI responds with its MCTS-chosen move.
+    pub fn step(&mut self) {
+        if self.winner.is_some() {
+            return;
+        }
+
+        if let Some(mv) = pick_heuristic_move(&self.state, Side::Player) {
+            self.state = self.state.apply(&mv, Side::Player);
+            self.log
+                .push(format!("Your pet #{} attacks!", mv.attacker_idx));
+            self.cached_root = self
+                .cached_root
+                .take()
+                .and_then(|mut node| node.children.remove(&mv));
+        }
+
+        if let Some(winner) = self.state.winner() {
+            self.winner = Some(winner);
+            return;
+        }
+
+        let mut root = self
+            .cached_root
+            .take()
+            .unwrap_or_else(|| Node::new(self.state.clone(), Side::Ai));
+        run_search(&mut root);
+
+        if let Some(mv) = most_visited_move(&root) {
+            self.log.push(format!(
+                "AI pet #{} attacks your pet #{}!",
+                mv.attacker_idx, mv.target_idx
+            ));
+            self.state = self.state.apply(&mv, Side::Ai);
+            self.cached_root = root.children.remove(&mv);
+        }
+
+        if let Some(winner) = self.state.winner() {
+            self.winner = Some(winner);
+        }
+    }
+}
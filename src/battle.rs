@@ -0,0 +1,99 @@
+use macroquad::rand;
+
+use crate::{combat, enemy_circle_center, GameState};
+
+/// Initiative a combatant needs to accumulate before it gets to act.
+const TURN_THRESHOLD: u32 = 1000;
+/// Scales `speed * dt` into the same integer units as `TURN_THRESHOLD`.
+const INITIATIVE_SCALE: f64 = 50.0;
+
+/// Advances every living combatant's initiative by one frame
+/// (`speed * INITIATIVE_SCALE` per second) and resolves any turn that crosses `TURN_THRESHOLD`: each ready
+/// pet attacks the active enemy, and the enemy, on its own initiative,
+/// attacks a random living pet.
+pub fn tick(gs: &mut GameState, dt: f64) {
+    let GameState {
+        player,
+        scene,
+        effects,
+        ..
+    } = gs;
+
+    let defender_def = scene.active_enemy.data.get_power().def;
+    let defender_attr = scene.active_enemy.data.attribute.clone();
+    let (popup_x, popup_y) = enemy_circle_center();
+    let mut total_pet_dmg: i64 = 0;
+
+    for (i, e) in player.active_team.active_team.iter_mut().enumerate() {
+        let Some(pet) = e else { continue };
+        if !pet.s_hp.is_alive() {
+            continue;
+        }
+
+        accumulate(&mut pet.turn_timer, pet.data.get_power().speed, dt);
+        if pet.turn_timer >= TURN_THRESHOLD {
+            pet.turn_timer -= TURN_THRESHOLD;
+            let dmg = combat::resolve_damage(
+                &pet.data.get_power(),
+                &pet.data.attribute,
+                defender_def,
+                &defender_attr,
+            );
+            total_pet_dmg += dmg;
+
+            let effectiveness = combat::classify(&pet.data.attribute, &defender_attr);
+            effects.spawn_damage_popup(dmg, effectiveness, popup_x, popup_y - 16.0 * i as f32);
+        }
+    }
+
+    if total_pet_dmg > 0 {
+        if let Some(defeated) = scene.do_damage(total_pet_dmg) {
+            gs.handle_defeat(defeated);
+        }
+    }
+
+    resolve_enemy_turn(gs, dt);
+}
+
+fn resolve_enemy_turn(gs: &mut GameState, dt: f64) {
+    let GameState { player, scene, .. } = gs;
+
+    let speed = scene.active_enemy.data.get_power().speed;
+    accumulate(&mut scene.active_enemy.turn_timer, speed, dt);
+    if scene.active_enemy.turn_timer < TURN_THRESHOLD {
+        return;
+    }
+    scene.active_enemy.turn_timer -= TURN_THRESHOLD;
+
+    let attacker_power = scene.active_enemy.data.get_power();
+    let attacker_attr = scene.active_enemy.data.attribute.clone();
+
+    let living: Vec<usize> = player
+        .active_team
+        .active_team
+        .iter()
+        .enumerate()
+        .filter_map(|(i, e)| e.as_ref().filter(|pet| pet.s_hp.is_alive()).map(|_| i))
+        .collect();
+
+    if living.is_empty() {
+        return;
+    }
+
+    let target_idx = living[rand::gen_range(0, living.len())];
+
+    if let Some(target) = &mut player.active_team.active_team[target_idx] {
+        let dmg = combat::resolve_damage(
+            &attacker_power,
+            &attacker_attr,
+            target.data.get_power().def,
+            &target.data.attribute,
+        );
+        target.s_hp.do_damage(dmg);
+    }
+}
+
+fn accumulate(turn_timer: &mut u32, speed: u64, dt: f64) {
+    let gained = (speed as f64 * dt * INITIATIVE_SCALE) as u32;
+    *turn_timer = turn_timer.saturating_add(gained);
+}
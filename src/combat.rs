@@ -0,0 +1,51 @@
+use crate::{EAttribute, Status};
+
+/// Rock-paper-scissors attribute triangle: VACCINE > VIRUS > DATA > VACCINE.
+/// `FREE` never gains or suffers an advantage.
+fn attribute_multiplier(attacker: &EAttribute, defender: &EAttribute) -> f64 {
+    use EAttribute::*;
+    match (attacker, defender) {
+        (FREE, _) | (_, FREE) => 1.0,
+        (VACCINE, VIRUS) | (VIRUS, DATA) | (DATA, VACCINE) => 1.5,
+        (VIRUS, VACCINE) | (DATA, VIRUS) | (VACCINE, DATA) => 0.75,
+        _ => 1.0,
+    }
+}
+
+/// How favorable an attribute matchup was, used to color damage popups.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Effectiveness {
+    Super,
+    Weak,
+    Neutral,
+}
+
+/// Classifies an attribute matchup the same way `resolve_damage` scales it.
+pub fn classify(attacker_attr: &EAttribute, defender_attr: &EAttribute) -> Effectiveness {
+    let multiplier = attribute_multiplier(attacker_attr, defender_attr);
+    if multiplier > 1.0 {
+        Effectiveness::Super
+    } else if multiplier < 1.0 {
+        Effectiveness::Weak
+    } else {
+        Effectiveness::Neutral
+    }
+}
+
+/// Attacker `str` mitigated by defender `def` (`str * str / (str + def)`:
+/// damage is halved when `def` equals `str` and keeps falling towards zero
+/// as `def` grows), then scaled by the attribute
+/// advantage between the two. Damage is always at least 1.
+pub fn resolve_damage(
+    attacker_status: &Status,
+    attacker_attr: &EAttribute,
+    defender_def: u64,
+    defender_attr: &EAttribute,
+) -> i64 {
+    let str_f = attacker_status.str as f64;
+    let def_f = defender_def as f64;
+    let mitigated = str_f * str_f / (str_f + def_f);
+
+    let dmg = mitigated * attribute_multiplier(attacker_attr, defender_attr);
+    (dmg.round() as i64).max(1)
+}
@@ -0,0 +1,120 @@
+use macroquad::rand;
+
+use crate::{EAttribute, ELevel, Status};
+
+/// Level at which a ROOKIE becomes eligible to digivolve into a CHAMPION.
+pub const ROOKIE_THRESHOLD: u32 = 11;
+/// Level at which a CHAMPION becomes eligible to digivolve into an ULTIMATE.
+pub const CHAMPION_THRESHOLD: u32 = 25;
+/// Digivolution fragments a pet's owner must have banked before a level-eligible
+/// pet actually digivolves. Lets fragments dropped by `loot::table_for` feed the
+/// mechanic instead of sitting unused in the inventory.
+pub const FRAGMENT_COST: u32 = 3;
+
+/// One branch of the digivolution graph: the form a pet can take next, the
+/// one-time stat boost it grants, and how much faster its stats grow afterward.
+pub struct Evolution {
+    pub next_level: ELevel,
+    pub next_attribute: Option<EAttribute>,
+    pub form_name: &'static str,
+    pub stat_boost: Status,
+    pub status_upgrade_bonus: Status,
+}
+
+/// The level a pet must reach before it can digivolve out of its current stage.
+pub fn threshold_for(level: &ELevel) -> Option<u32> {
+    match level {
+        ELevel::ROOKIE => Some(ROOKIE_THRESHOLD),
+        ELevel::CHAMPION => Some(CHAMPION_THRESHOLD),
+        ELevel::ULTIMATE => None,
+    }
+}
+
+/// The possible next forms for a pet at `level` with `attribute`, modeled as a
+/// small directed graph: some branches are fixed, others (an attribute-less
+/// FREE pet) fork into several paths with their own resulting attribute.
+fn next_forms(level: &ELevel, attribute: &EAttribute) -> Vec<Evolution> {
+    use EAttribute::*;
+    use ELevel::*;
+
+    match (level, attribute) {
+        (ROOKIE, VACCINE) => vec![Evolution {
+            next_level: CHAMPION,
+            next_attribute: None,
+            form_name: "Guardromon",
+            stat_boost: Status::new(30, 40, 10),
+            status_upgrade_bonus: Status::new(2, 3, 1),
+        }],
+        (ROOKIE, VIRUS) => vec![Evolution {
+            next_level: CHAMPION,
+            next_attribute: None,
+            form_name: "Devimon",
+            stat_boost: Status::new(45, 15, 15),
+            status_upgrade_bonus: Status::new(3, 1, 2),
+        }],
+        (ROOKIE, DATA) => vec![Evolution {
+            next_level: CHAMPION,
+            next_attribute: None,
+            form_name: "Kabuterimon",
+            stat_boost: Status::new(25, 25, 25),
+            status_upgrade_bonus: Status::new(2, 2, 2),
+        }],
+        (ROOKIE, FREE) => vec![
+            Evolution {
+                next_level: CHAMPION,
+                next_attribute: Some(DATA),
+                form_name: "Greymon",
+                stat_boost: Status::new(35, 20, 15),
+                status_upgrade_bonus: Status::new(2, 2, 1),
+            },
+            Evolution {
+                next_level: CHAMPION,
+                next_attribute: Some(VIRUS),
+                form_name: "Tyrannomon",
+                stat_boost: Status::new(40, 15, 10),
+                status_upgrade_bonus: Status::new(3, 1, 1),
+            },
+        ],
+        (CHAMPION, VACCINE) => vec![Evolution {
+            next_level: ULTIMATE,
+            next_attribute: None,
+            form_name: "WarGreymon",
+            stat_boost: Status::new(70, 60, 30),
+            status_upgrade_bonus: Status::new(4, 4, 2),
+        }],
+        (CHAMPION, VIRUS) => vec![Evolution {
+            next_level: ULTIMATE,
+            next_attribute: None,
+            form_name: "Myotismon",
+            stat_boost: Status::new(90, 30, 35),
+            status_upgrade_bonus: Status::new(5, 2, 3),
+        }],
+        (CHAMPION, DATA) => vec![Evolution {
+            next_level: ULTIMATE,
+            next_attribute: None,
+            form_name: "HerculesKabuterimon",
+            stat_boost: Status::new(55, 55, 45),
+            status_upgrade_bonus: Status::new(3, 3, 3),
+        }],
+        (CHAMPION, FREE) => vec![Evolution {
+            next_level: ULTIMATE,
+            next_attribute: Some(VACCINE),
+            form_name: "MetalGreymon",
+            stat_boost: Status::new(65, 45, 25),
+            status_upgrade_bonus: Status::new(4, 3, 2),
+        }],
+        (ULTIMATE, _) => vec![],
+    }
+}
+
+/// Picks one of the possible next forms for `level`/`attribute`, letting a
+/// branching (FREE) pet evolve down different paths.
+pub fn pick_evolution(level: &ELevel, attribute: &EAttribute) -> Option<Evolution> {
+    let candidates = next_forms(level, attribute);
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let idx = rand::gen_range(0, candidates.len());
+    candidates.into_iter().nth(idx)
+}
@@ -0,0 +1,81 @@
+use macroquad::rand;
+
+use crate::Status;
+
+/// A single reward a loot table can yield. `Nothing` is a legitimate entry so
+/// a table can be weighted towards "no drop" without special-casing it.
+#[derive(Clone, Debug)]
+pub enum Item {
+    Nothing,
+    StatBoost(Status),
+    DigivolutionFragment,
+}
+
+struct LootEntry {
+    item: Item,
+    weight: u32,
+}
+
+pub struct LootTable {
+    entries: Vec<LootEntry>,
+}
+
+impl LootTable {
+    /// Sums the entry weights, draws a uniform value in `[0, total)`, then walks
+    /// the entries accumulating weight until the draw falls under the running total.
+    pub fn roll(&self) -> Item {
+        let total_weight: u32 = self.entries.iter().map(|e| e.weight).sum();
+        if total_weight == 0 {
+            return Item::Nothing;
+        }
+
+        let mut draw = rand::gen_range(0, total_weight);
+
+        for entry in &self.entries {
+            if draw < entry.weight {
+                return entry.item.clone();
+            }
+            draw -= entry.weight;
+        }
+
+        Item::Nothing
+    }
+}
+
+/// The loot table a `Battler` can be flagged with, looked up by name.
+pub fn table_for(name: &str) -> LootTable {
+    match name {
+        "boss" => LootTable {
+            entries: vec![
+                LootEntry {
+                    item: Item::Nothing,
+                    weight: 20,
+                },
+                LootEntry {
+                    item: Item::StatBoost(Status::new(3, 3, 3)),
+                    weight: 40,
+                },
+                LootEntry {
+                    item: Item::DigivolutionFragment,
+                    weight: 40,
+                },
+            ],
+        },
+        _ => LootTable {
+            entries: vec![
+                LootEntry {
+                    item: Item::Nothing,
+                    weight: 60,
+                },
+                LootEntry {
+                    item: Item::StatBoost(Status::new(1, 1, 1)),
+                    weight: 30,
+                },
+                LootEntry {
+                    item: Item::DigivolutionFragment,
+                    weight: 10,
+                },
+            ],
+        },
+    }
+}
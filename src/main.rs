@@ -1,14 +1,22 @@
 use macroquad::prelude::*;
-use rand::RandGenerator;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+mod arena;
+mod battle;
+mod combat;
+mod digivolution;
+mod loot;
+mod particles;
+mod persistence;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 enum ELevel {
     ROOKIE,
     CHAMPION,
     ULTIMATE,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 enum EAttribute {
     VACCINE,
     DATA,
@@ -16,7 +24,7 @@ enum EAttribute {
     FREE,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct HpSystem {
     hp_base: u64,
     hp: u64,
@@ -24,8 +32,7 @@ struct HpSystem {
 
 impl HpSystem {
     fn new_rand_hp() -> Self {
-        const RNG: rand::RandGenerator = rand::RandGenerator::new();
-        let hp_base = RNG.gen_range(100, 1000);
+        let hp_base = rand::gen_range(100, 1000);
         Self {
             hp_base,
             hp: hp_base,
@@ -51,7 +58,7 @@ impl HpSystem {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct LevelStatusSystem {
     level: u32,
     total_exp: u64,
@@ -72,11 +79,14 @@ impl LevelStatusSystem {
         0
     }
 
-    fn update_exp(&mut self, exp: u64) {
+    /// Adds `exp`, returning `true` if it was enough to raise the level.
+    fn update_exp(&mut self, exp: u64) -> bool {
         self.total_exp += exp;
         if self.total_exp > self.formula_lvlup() && self.level < 999 {
             self.level += 1;
+            return true;
         }
+        false
     }
 
     fn given_exp(&self, lvl: u32) -> u64 {
@@ -108,7 +118,7 @@ impl Default for LevelStatusSystem {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct Status {
     str: u64,
     def: u64,
@@ -130,31 +140,30 @@ impl Status {
         Self { str, def, speed }
     }
 
-    fn sum(&mut self, status_upg: &Status) -> &Status {
-        self.str += status_upg.str;
-        self.def += status_upg.def;
-        self.speed += status_upg.speed;
-
-        self
+    /// Adds another status' values into this one in place, e.g. for a digivolution boost.
+    fn boost(&mut self, other: &Status) {
+        self.str += other.str;
+        self.def += other.def;
+        self.speed += other.speed;
     }
 
     fn rand_status() -> Self {
-        let RNG = rand::RandGenerator::new();
         Self {
-            str: RNG.gen_range(5, 25),
-            def: RNG.gen_range(5, 25),
-            speed: RNG.gen_range(5, 25),
+            str: rand::gen_range(5, 25),
+            def: rand::gen_range(5, 25),
+            speed: rand::gen_range(5, 25),
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct BytePet {
     id: u8,
     s_level: LevelStatusSystem,
     byte_level: ELevel,
     attribute: EAttribute,
     status: Status,
+    evolved_name: Option<String>,
 }
 
 impl Default for BytePet {
@@ -165,6 +174,7 @@ impl Default for BytePet {
             byte_level: ELevel::ROOKIE,
             attribute: EAttribute::FREE,
             status: Status::default(),
+            evolved_name: None,
         }
     }
 }
@@ -183,6 +193,7 @@ impl BytePet {
             s_level: level,
             attribute,
             status,
+            evolved_name: None,
         }
     }
 
@@ -195,14 +206,51 @@ impl BytePet {
 
         total_status
     }
+
+    /// Applies `exp`, digivolving the pet if it just crossed its stage's level
+    /// threshold and its owner has banked enough `fragments`. Returns `true`
+    /// when a digivolution happened; the pet stays eligible (and keeps
+    /// leveling) until enough fragments have dropped to pay the cost.
+    fn gain_exp(&mut self, exp: u64, fragments: &mut u32) -> bool {
+        if !self.s_level.update_exp(exp) {
+            return false;
+        }
+
+        let Some(threshold) = digivolution::threshold_for(&self.byte_level) else {
+            return false;
+        };
+        if self.s_level.level < threshold {
+            return false;
+        }
+        if *fragments < digivolution::FRAGMENT_COST {
+            return false;
+        }
+
+        let Some(evolution) = digivolution::pick_evolution(&self.byte_level, &self.attribute)
+        else {
+            return false;
+        };
+
+        *fragments -= digivolution::FRAGMENT_COST;
+        self.status.boost(&evolution.stat_boost);
+        self.s_level.status_upgrade.boost(&evolution.status_upgrade_bonus);
+        self.byte_level = evolution.next_level;
+        if let Some(next_attribute) = evolution.next_attribute {
+            self.attribute = next_attribute;
+        }
+        self.evolved_name = Some(evolution.form_name.to_owned());
+
+        true
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct Battler {
     s_hp: HpSystem,
     name: String,
     turn_timer: u32,
     data: BytePet,
+    loot_table: String,
 }
 
 impl Default for Battler {
@@ -213,6 +261,7 @@ impl Default for Battler {
             name: "PHoldermon".to_owned(),
             turn_timer: 0,
             data: BytePet::default(),
+            loot_table: "basic".to_owned(),
         }
     }
 }
@@ -227,32 +276,46 @@ impl Battler {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 struct TeamManager {
     active_team: [Option<Battler>; 3],
 }
 
 impl TeamManager {
-    fn get_team_power(&mut self) -> Status {
-        let mut team_status = Status {
-            str: 0,
-            def: 0,
-            speed: 0,
-        };
+    /// Sums each living pet's attribute- and defense-mitigated hit against `defender`.
+    fn get_team_damage(&mut self, defender_def: u64, defender_attr: &EAttribute) -> i64 {
+        let mut total_dmg: i64 = 0;
 
         for e in self.active_team.iter_mut() {
             if let Some(x) = e {
-                team_status.sum(&x.data.get_power());
+                if !x.s_hp.is_alive() {
+                    continue;
+                }
+                total_dmg += combat::resolve_damage(
+                    &x.data.get_power(),
+                    &x.data.attribute,
+                    defender_def,
+                    defender_attr,
+                );
             }
         }
 
-        team_status
+        total_dmg
     }
 }
 
+#[derive(Default, Serialize, Deserialize)]
+struct Inventory {
+    stat_boosts_collected: u32,
+    digivolution_fragments: u32,
+}
+
+#[derive(Serialize, Deserialize)]
 struct Player {
     clicks: u64,
     total_defeated: u64,
     active_team: TeamManager,
+    inventory: Inventory,
 }
 
 impl Default for Player {
@@ -264,6 +327,7 @@ impl Default for Player {
             active_team: TeamManager {
                 active_team: [EMPTY_PET; 3],
             },
+            inventory: Inventory::default(),
         }
     }
 }
@@ -280,17 +344,59 @@ impl Player {
     }
 
     fn add_exp_to_pets(&mut self, exp: u64) {
+        let mut fragments = self.inventory.digivolution_fragments;
         for e in self.active_team.active_team.iter_mut() {
             if let Some(x) = e {
-                x.data.s_level.update_exp(exp);
+                x.data.gain_exp(exp, &mut fragments);
+            }
+        }
+        self.inventory.digivolution_fragments = fragments;
+    }
+
+    /// Records the drop in the inventory and, for consumables, applies its
+    /// effect to the whole team right away.
+    fn apply_item(&mut self, item: loot::Item) {
+        match item {
+            loot::Item::Nothing => {}
+            loot::Item::StatBoost(boost) => {
+                self.inventory.stat_boosts_collected += 1;
+                for e in self.active_team.active_team.iter_mut() {
+                    if let Some(x) = e {
+                        x.data.status.boost(&boost);
+                    }
+                }
+            }
+            loot::Item::DigivolutionFragment => {
+                self.inventory.digivolution_fragments += 1;
             }
         }
     }
 
-    fn get_power(&mut self) -> i64 {
-        let mut dmg: i64 = 0;
-        dmg = dmg + self.active_team.get_team_power().str as i64;
-        dmg
+    /// Heals every team member back to full HP, so a fainted pet can fight again.
+    fn revive_team(&mut self) {
+        for e in self.active_team.active_team.iter_mut() {
+            if let Some(x) = e {
+                x.s_hp.hp = x.s_hp.hp_base;
+            }
+        }
+    }
+
+    /// The attribute of the first living pet, used to color the click-burst
+    /// damage popup. Defaults to `FREE` (neutral) if the whole team has fainted.
+    fn first_alive_attribute(&self) -> EAttribute {
+        self.active_team
+            .active_team
+            .iter()
+            .filter_map(|e| e.as_ref())
+            .find(|pet| pet.s_hp.is_alive())
+            .map(|pet| pet.data.attribute.clone())
+            .unwrap_or(EAttribute::FREE)
+    }
+
+    fn get_power(&mut self, defender: &Battler) -> i64 {
+        let defender_power = defender.data.get_power();
+        self.active_team
+            .get_team_damage(defender_power.def, &defender.data.attribute)
     }
 }
 
@@ -325,29 +431,43 @@ impl Scene {
 struct GameState {
     player: Player,
     scene: Scene,
-    frame_time: f64,
+    arena_match: Option<arena::ArenaMatch>,
+    effects: particles::EffectsState,
 }
 
 impl GameState {
-    fn update_time(&mut self, dt: f64) {
-        self.frame_time += dt;
-    }
-
     fn manual_dmg(&mut self) {
-        let e = self.scene.do_damage(self.player.get_power());
+        let dmg = self.player.get_power(&self.scene.active_enemy);
+
+        let effectiveness = combat::classify(
+            &self.player.first_alive_attribute(),
+            &self.scene.active_enemy.data.attribute,
+        );
+        let (x, y) = enemy_circle_center();
+        self.effects.spawn_damage_popup(dmg, effectiveness, x, y);
+
+        let e = self.scene.do_damage(dmg);
         self.player.clicks += 1;
 
         if let Some(x) = e {
-            self.player.add_exp_to_pets(x.data.s_level.given_exp(1));
-            self.player.total_defeated += 1;
+            self.handle_defeat(x);
         }
     }
 
-    fn auto_dmg(&mut self, dt: f64) {
-        self.update_time(dt);
-        if self.frame_time >= 0.6 {
-            self.frame_time -= 0.6;
-            self.manual_dmg();
+    /// Awards exp, rolls the loot table, tracks the kill count, shakes the
+    /// enemy circle and autosaves periodically. Shared by manual clicks and the
+    /// scheduled autobattle turns.
+    fn handle_defeat(&mut self, defeated: Battler) {
+        self.player
+            .add_exp_to_pets(defeated.data.s_level.given_exp(1));
+        self.player.total_defeated += 1;
+        self.effects.trigger_enemy_shake();
+
+        let drop = loot::table_for(&defeated.loot_table).roll();
+        self.player.apply_item(drop);
+
+        if persistence::should_autosave(self.player.total_defeated) {
+            let _ = persistence::save_game(&self.player);
         }
     }
 }
@@ -365,38 +485,59 @@ fn window_conf() -> Conf {
 
 #[macroquad::main(window_conf)]
 async fn main() {
+    // `RandGenerator::new()` always starts from the same fixed seed, so the
+    // shared `rand::gen_range` calls used for loot rolls, digivolution branch
+    // picks, and arena rollouts need a real seed once at startup instead.
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+    rand::srand(seed);
+
     let initial_scene = Scene {
         possible_enemies: vec![Battler::default()],
         active_enemy: Battler::default(),
         name: "Test1".to_owned(),
     };
 
-    let mut player = Player {
-        ..Default::default()
-    };
+    let player = persistence::load_game().unwrap_or_else(|| {
+        let mut player = Player {
+            ..Default::default()
+        };
 
-    let pet2 = Battler {
-        name: "Bertrano".to_owned(),
-        ..Default::default()
-    };
-    let pet3 = Battler {
-        name: "Fipongo".to_owned(),
-        ..Default::default()
-    };
-    player.add_pet(Battler::default());
-    player.add_pet(pet2);
-    player.add_pet(pet3);
+        let pet2 = Battler {
+            name: "Bertrano".to_owned(),
+            ..Default::default()
+        };
+        let pet3 = Battler {
+            name: "Fipongo".to_owned(),
+            ..Default::default()
+        };
+        player.add_pet(Battler::default());
+        player.add_pet(pet2);
+        player.add_pet(pet3);
+
+        player
+    });
 
     let mut gs = GameState {
         player,
         scene: initial_scene,
-        frame_time: 0.,
+        arena_match: None,
+        effects: particles::EffectsState::default(),
     };
 
+    prevent_quit();
     loop {
         clear_background(BLACK);
         update(&mut gs).await;
         draw(&mut gs).await;
+
+        if is_quit_requested() {
+            let _ = persistence::save_game(&gs.player);
+            break;
+        }
+
         next_frame().await;
     }
 }
@@ -406,11 +547,74 @@ async fn update(gs: &mut GameState) {
         gs.manual_dmg();
     }
 
-    if is_key_down(KeyCode::A) {
-        gs.auto_dmg(get_frame_time() as f64);
+    battle::tick(gs, get_frame_time() as f64);
+    gs.effects.update(get_frame_time());
+
+    if is_key_pressed(KeyCode::R) {
+        gs.player.revive_team();
+    }
+
+    if is_key_pressed(KeyCode::P) {
+        let finished = matches!(&gs.arena_match, Some(m) if m.winner.is_some());
+        if finished {
+            gs.arena_match = None;
+        } else if let Some(arena_match) = &mut gs.arena_match {
+            arena_match.step();
+        } else {
+            gs.arena_match = Some(start_arena_match(&gs.player));
+        }
     }
 }
 
+/// Builds a fresh arena match pitting the player's current team against a
+/// small rival team, one VACCINE, one VIRUS and one DATA pet.
+fn start_arena_match(player: &Player) -> arena::ArenaMatch {
+    let ai_team = vec![
+        Battler {
+            name: "ArenaRival1".to_owned(),
+            data: BytePet::new(
+                1,
+                LevelStatusSystem::default(),
+                ELevel::ROOKIE,
+                EAttribute::VACCINE,
+                Status::rand_status(),
+            ),
+            ..Default::default()
+        },
+        Battler {
+            name: "ArenaRival2".to_owned(),
+            data: BytePet::new(
+                2,
+                LevelStatusSystem::default(),
+                ELevel::ROOKIE,
+                EAttribute::VIRUS,
+                Status::rand_status(),
+            ),
+            ..Default::default()
+        },
+        Battler {
+            name: "ArenaRival3".to_owned(),
+            data: BytePet::new(
+                3,
+                LevelStatusSystem::default(),
+                ELevel::ROOKIE,
+                EAttribute::DATA,
+                Status::rand_status(),
+            ),
+            ..Default::default()
+        },
+    ];
+
+    let player_team: Vec<Battler> = player
+        .active_team
+        .active_team
+        .iter()
+        .filter_map(|e| e.clone())
+        .collect();
+
+    arena::ArenaMatch::new(&player_team, &ai_team)
+}
+
 async fn draw(gs: &mut GameState) {
     draw_text(
         &format!(
@@ -436,6 +640,7 @@ async fn draw(gs: &mut GameState) {
 
     draw_enemy(gs).await;
     draw_allies_data(gs).await;
+    draw_arena(gs).await;
 }
 
 async fn draw_enemy(gs: &mut GameState) {
@@ -470,16 +675,35 @@ async fn draw_enemy(gs: &mut GameState) {
         26.,
         RED,
     );
-    draw_circle_lines(screen_width() / 2., screen_height() / 4., 80., 4., RED);
+    let (center_x, center_y) = enemy_circle_center();
+    draw_circle_lines(
+        center_x + gs.effects.enemy_shake_offset(),
+        center_y,
+        80.,
+        4.,
+        RED,
+    );
+    gs.effects.draw();
+}
+
+/// Screen-space center of the enemy circle, shared by its rendering and the
+/// damage popups/shake that appear around it.
+fn enemy_circle_center() -> (f32, f32) {
+    (screen_width() / 2., screen_height() / 4.)
 }
 
 async fn draw_allies_data(gs: &mut GameState) {
     for (i, e) in gs.player.active_team.active_team.iter().enumerate() {
         if let Some(x) = e {
+            let form = match &x.data.evolved_name {
+                Some(name) => format!(" [{} - {:?}]", name, x.data.byte_level),
+                None => format!(" [{:?}]", x.data.byte_level),
+            };
             draw_text(
                 &format!(
-                    "{} / EXP: {:?}/NEXT: {:?} - LVL: {:?}",
+                    "{}{} / EXP: {:?}/NEXT: {:?} - LVL: {:?}",
                     x.name,
+                    form,
                     x.data.s_level.total_exp,
                     x.data.s_level.to_next_level(),
                     x.data.s_level.level
@@ -504,3 +728,25 @@ async fn draw_allies_data(gs: &mut GameState) {
         }
     }
 }
+
+async fn draw_arena(gs: &mut GameState) {
+    let Some(arena_match) = &gs.arena_match else {
+        draw_text("Press P to start an arena match", 20., 580., 16., YELLOW);
+        return;
+    };
+
+    if let Some(winner) = arena_match.winner {
+        let result = match winner {
+            arena::Side::Player => "You win the arena match!",
+            arena::Side::Ai => "You lost the arena match.",
+        };
+        draw_text(result, 20., 580., 18., YELLOW);
+        draw_text("Press P to clear", 20., 600., 16., YELLOW);
+        return;
+    }
+
+    draw_text("Press P to advance the arena match", 20., 580., 16., YELLOW);
+    for (i, line) in arena_match.log.iter().rev().take(3).rev().enumerate() {
+        draw_text(line, 20., 600. + 16. * i as f32, 14., YELLOW);
+    }
+}
@@ -0,0 +1,104 @@
+use macroquad::prelude::*;
+
+use crate::combat::Effectiveness;
+
+/// How long a floating damage popup stays on screen before it fully fades.
+const POPUP_LIFETIME: f32 = 0.5;
+/// How far a popup rises over its lifetime, in pixels.
+const RISE_DISTANCE: f32 = 40.0;
+
+/// Brief shake applied to the enemy circle when it's defeated.
+const SHAKE_DURATION: f32 = 0.3;
+const SHAKE_MAGNITUDE: f32 = 6.0;
+
+fn color_for(effectiveness: Effectiveness) -> Color {
+    match effectiveness {
+        Effectiveness::Super => ORANGE,
+        Effectiveness::Weak => GRAY,
+        Effectiveness::Neutral => YELLOW,
+    }
+}
+
+struct DamagePopup {
+    text: String,
+    color: Color,
+    x: f32,
+    y: f32,
+    age: f32,
+}
+
+impl DamagePopup {
+    fn is_expired(&self) -> bool {
+        self.age >= POPUP_LIFETIME
+    }
+
+    fn draw(&self) {
+        let progress = self.age / POPUP_LIFETIME;
+        let mut color = self.color;
+        color.a = 1.0 - progress;
+        draw_text(&self.text, self.x, self.y - RISE_DISTANCE * progress, 24., color);
+    }
+}
+
+struct EnemyShake {
+    age: f32,
+}
+
+impl EnemyShake {
+    fn is_expired(&self) -> bool {
+        self.age >= SHAKE_DURATION
+    }
+
+    fn offset(&self) -> f32 {
+        let decay = 1.0 - self.age / SHAKE_DURATION;
+        (self.age * 40.0).sin() * SHAKE_MAGNITUDE * decay
+    }
+}
+
+/// Active floating damage numbers and the enemy defeat shake, advanced once per
+/// frame and rendered above the enemy.
+#[derive(Default)]
+pub struct EffectsState {
+    popups: Vec<DamagePopup>,
+    enemy_shake: Option<EnemyShake>,
+}
+
+impl EffectsState {
+    pub fn spawn_damage_popup(&mut self, dmg: i64, effectiveness: Effectiveness, x: f32, y: f32) {
+        self.popups.push(DamagePopup {
+            text: dmg.to_string(),
+            color: color_for(effectiveness),
+            x,
+            y,
+            age: 0.0,
+        });
+    }
+
+    pub fn trigger_enemy_shake(&mut self) {
+        self.enemy_shake = Some(EnemyShake { age: 0.0 });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for popup in self.popups.iter_mut() {
+            popup.age += dt;
+        }
+        self.popups.retain(|p| !p.is_expired());
+
+        if let Some(shake) = &mut self.enemy_shake {
+            shake.age += dt;
+            if shake.is_expired() {
+                self.enemy_shake = None;
+            }
+        }
+    }
+
+    pub fn enemy_shake_offset(&self) -> f32 {
+        self.enemy_shake.as_ref().map_or(0.0, EnemyShake::offset)
+    }
+
+    pub fn draw(&self) {
+        for popup in &self.popups {
+            popup.draw();
+        }
+    }
+}
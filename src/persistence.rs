@@ -0,0 +1,25 @@
+use std::fs;
+use std::io;
+
+use crate::Player;
+
+const SAVE_PATH: &str = "save.json";
+const AUTOSAVE_DEFEAT_INTERVAL: u64 = 10;
+
+/// Writes the player's progress to `SAVE_PATH` as pretty-printed JSON.
+pub fn save_game(player: &Player) -> io::Result<()> {
+    let json =
+        serde_json::to_string_pretty(player).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    fs::write(SAVE_PATH, json)
+}
+
+/// Loads a previously saved player, if `SAVE_PATH` exists and parses cleanly.
+pub fn load_game() -> Option<Player> {
+    let json = fs::read_to_string(SAVE_PATH).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// True every `AUTOSAVE_DEFEAT_INTERVAL` defeats, used to trigger a periodic autosave.
+pub fn should_autosave(total_defeated: u64) -> bool {
+    total_defeated > 0 && total_defeated % AUTOSAVE_DEFEAT_INTERVAL == 0
+}